@@ -6,10 +6,11 @@ use std::error::Error as StdError;
 
 pub struct HuffmanTable {
     decode: Vec<Entry>,
+    decode_double: Vec<DoubleEntry>,
 
-    weights: Vec<u8>,
+    pub(crate) weights: Vec<u8>,
     pub max_num_bits: u8,
-    bits: Vec<u8>,
+    pub(crate) bits: Vec<u8>,
     bit_ranks: Vec<u32>,
     rank_indexes: Vec<usize>,
 
@@ -55,6 +56,18 @@ pub enum HuffmanTableError {
     MaxBitsTooHigh {
         got: u8,
     },
+    FourStreamSizesExceedSource {
+        declared: usize,
+        available: usize,
+    },
+    FourStreamSymbolCountUnderflow {
+        total_symbols: usize,
+        symbols_per_stream: usize,
+    },
+    FourStreamOverconsumed {
+        stream_index: u8,
+        bits_remaining: isize,
+    },
 }
 
 #[cfg(feature = "std")]
@@ -141,6 +154,33 @@ impl core::fmt::Display for HuffmanTableError {
                     got, MAX_MAX_NUM_BITS,
                 )
             }
+            HuffmanTableError::FourStreamSizesExceedSource { declared, available } => {
+                write!(
+                    f,
+                    "4-stream jump table declares {} bytes of stream data but only {} bytes are available in the source",
+                    declared, available,
+                )
+            }
+            HuffmanTableError::FourStreamSymbolCountUnderflow {
+                total_symbols,
+                symbols_per_stream,
+            } => {
+                write!(
+                    f,
+                    "total symbol count {} is too small to split across 4 streams of {} symbols each",
+                    total_symbols, symbols_per_stream,
+                )
+            }
+            HuffmanTableError::FourStreamOverconsumed {
+                stream_index,
+                bits_remaining,
+            } => {
+                write!(
+                    f,
+                    "stream {} read past its declared size ({} bits remaining after decoding); source is probably corrupted",
+                    stream_index, bits_remaining,
+                )
+            }
         }
     }
 }
@@ -157,6 +197,19 @@ impl From<FSEDecoderError> for HuffmanTableError {
     }
 }
 
+impl From<HuffmanDecoderError> for HuffmanTableError {
+    fn from(val: HuffmanDecoderError) -> Self {
+        match val {
+            HuffmanDecoderError::GetBitsError(e) => Self::GetBitsError(e),
+            // `decode_4streams`, the only caller that needs this conversion, only ever calls
+            // `init_state`/`next_state`, which never produce this variant.
+            HuffmanDecoderError::DoubleDecodeTableNotBuilt => {
+                unreachable!("init_state/next_state never return DoubleDecodeTableNotBuilt")
+            }
+        }
+    }
+}
+
 impl From<FSETableError> for HuffmanTableError {
     fn from(val: FSETableError) -> Self {
         Self::FSETableError(val)
@@ -172,12 +225,17 @@ pub struct HuffmanDecoder<'table> {
 #[non_exhaustive]
 pub enum HuffmanDecoderError {
     GetBitsError(GetBitsError),
+    DoubleDecodeTableNotBuilt,
 }
 
 impl core::fmt::Display for HuffmanDecoderError {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             HuffmanDecoderError::GetBitsError(e) => write!(f, "{:?}", e),
+            HuffmanDecoderError::DoubleDecodeTableNotBuilt => write!(
+                f,
+                "decode_pair/next_pair need HuffmanTable::build_double_decode_table to have been called first"
+            ),
         }
     }
 }
@@ -187,6 +245,7 @@ impl StdError for HuffmanDecoderError {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
         match self {
             HuffmanDecoderError::GetBitsError(source) => Some(source),
+            HuffmanDecoderError::DoubleDecodeTableNotBuilt => None,
         }
     }
 }
@@ -203,7 +262,15 @@ pub struct Entry {
     num_bits: u8,
 }
 
-const MAX_MAX_NUM_BITS: u8 = 11;
+/// A double-symbol decode table entry, following klauspost's `dEntryDouble` approach.
+#[derive(Copy, Clone)]
+struct DoubleEntry {
+    symbols: [u8; 2],
+    num_symbols: u8,
+    bits_consumed: u8,
+}
+
+pub(crate) const MAX_MAX_NUM_BITS: u8 = 11;
 
 fn highest_bit_set(x: u32) -> u32 {
     assert!(x > 0);
@@ -247,6 +314,49 @@ impl<'t> HuffmanDecoder<'t> {
         self.state |= new_bits;
         Ok(num_bits)
     }
+
+    /// Returns up to two symbols decodable from the current state without consuming any bits,
+    /// using the table built by [`HuffmanTable::build_double_decode_table`].
+    pub fn decode_pair(&self) -> Result<(u8, Option<u8>), HuffmanDecoderError> {
+        if self.table.decode_double.is_empty() {
+            return Err(HuffmanDecoderError::DoubleDecodeTableNotBuilt);
+        }
+        let entry = self.table.decode_double[self.state as usize];
+        let second = (entry.num_symbols == 2).then_some(entry.symbols[1]);
+        Ok((entry.symbols[0], second))
+    }
+
+    /// Decodes up to two symbols per bit-reader round trip, falling back to a single-symbol
+    /// step near the end of the stream. Returns the first symbol, the second symbol if one was
+    /// decoded, and the number of bits consumed.
+    pub fn next_pair(
+        &mut self,
+        br: &mut BitReaderReversed<'_>,
+    ) -> Result<(u8, Option<u8>, u8), HuffmanDecoderError> {
+        if self.table.decode_double.is_empty() {
+            return Err(HuffmanDecoderError::DoubleDecodeTableNotBuilt);
+        }
+
+        let entry = self.table.decode_double[self.state as usize];
+        let (symbols, num_symbols, num_bits) =
+            if entry.num_symbols == 2 && br.bits_remaining() < entry.bits_consumed as isize {
+                // not enough bits left in the stream for the full double decode; fall back to
+                // a single-symbol step so `bits_remaining()`'s end-of-stream guard still
+                // governs termination instead of us reading past the end of the stream.
+                let single = self.table.decode[self.state as usize];
+                ([single.symbol, 0], 1, single.num_bits)
+            } else {
+                (entry.symbols, entry.num_symbols, entry.bits_consumed)
+            };
+
+        let new_bits = br.get_bits(num_bits)?;
+        self.state <<= num_bits;
+        self.state &= self.table.decode.len() as u64 - 1;
+        self.state |= new_bits;
+
+        let second = (num_symbols == 2).then_some(symbols[1]);
+        Ok((symbols[0], second, num_bits))
+    }
 }
 
 impl Default for HuffmanTable {
@@ -259,6 +369,7 @@ impl HuffmanTable {
     pub fn new() -> HuffmanTable {
         HuffmanTable {
             decode: Vec::new(),
+            decode_double: Vec::new(),
 
             weights: Vec::with_capacity(256),
             max_num_bits: 0,
@@ -272,6 +383,7 @@ impl HuffmanTable {
     pub fn reinit_from(&mut self, other: &Self) {
         self.reset();
         self.decode.extend_from_slice(&other.decode);
+        self.decode_double.extend_from_slice(&other.decode_double);
         self.weights.extend_from_slice(&other.weights);
         self.max_num_bits = other.max_num_bits;
         self.bits.extend_from_slice(&other.bits);
@@ -281,6 +393,7 @@ impl HuffmanTable {
 
     pub fn reset(&mut self) {
         self.decode.clear();
+        self.decode_double.clear();
         self.weights.clear();
         self.max_num_bits = 0;
         self.bits.clear();
@@ -291,12 +404,66 @@ impl HuffmanTable {
 
     pub fn build_decoder(&mut self, source: &[u8]) -> Result<u32, HuffmanTableError> {
         self.decode.clear();
+        self.decode_double.clear();
 
         let bytes_used = self.read_weights(source)?;
         self.build_table_from_weights()?;
         Ok(bytes_used)
     }
 
+    /// Builds the double-symbol decode table used by [`HuffmanDecoder::decode_pair`] /
+    /// [`HuffmanDecoder::next_pair`], on top of an already-built single-symbol table.
+    ///
+    /// This is opt-in: callers that only ever decode one symbol at a time (e.g. tests, or
+    /// literal sections too small to be worth it) can skip the extra table-build cost by
+    /// simply never calling this. No-op if `self.decode` hasn't been built yet.
+    pub fn build_double_decode_table(&mut self) {
+        if self.decode.is_empty() {
+            self.decode_double.clear();
+            return;
+        }
+        let mask = self.decode.len() - 1;
+
+        self.decode_double.clear();
+        self.decode_double.reserve(self.decode.len());
+
+        for idx in 0..self.decode.len() {
+            let first = self.decode[idx];
+
+            if first.num_bits == 0 {
+                self.decode_double.push(DoubleEntry {
+                    symbols: [first.symbol, 0],
+                    num_symbols: 1,
+                    bits_consumed: first.num_bits,
+                });
+                continue;
+            }
+
+            // the bits of `idx` beyond `first.num_bits` are still-unconsumed lookahead bits;
+            // shifting them to the top (and zero-filling the newly-empty low bits, which are
+            // "don't care": every entry sharing a code's full-length prefix decodes the same
+            // way regardless of its suffix) lets us look up a second symbol using only bits
+            // we've already buffered, no extra bit-reader round trip required.
+            let second_idx = (idx << first.num_bits) & mask;
+            let second = self.decode[second_idx];
+
+            let total_bits = first.num_bits + second.num_bits;
+            if second.num_bits > 0 && total_bits as usize <= self.max_num_bits as usize {
+                self.decode_double.push(DoubleEntry {
+                    symbols: [first.symbol, second.symbol],
+                    num_symbols: 2,
+                    bits_consumed: total_bits,
+                });
+            } else {
+                self.decode_double.push(DoubleEntry {
+                    symbols: [first.symbol, 0],
+                    num_symbols: 1,
+                    bits_consumed: first.num_bits,
+                });
+            }
+        }
+    }
+
     fn read_weights(&mut self, source: &[u8]) -> Result<u32, HuffmanTableError> {
         use HuffmanTableError as err;
 
@@ -434,7 +601,7 @@ impl HuffmanTable {
         Ok(bytes_read as u32)
     }
 
-    fn build_table_from_weights(&mut self) -> Result<(), HuffmanTableError> {
+    pub(crate) fn build_table_from_weights(&mut self) -> Result<(), HuffmanTableError> {
         use HuffmanTableError as err;
 
         self.bits.clear();
@@ -529,3 +696,336 @@ impl HuffmanTable {
         Ok(())
     }
 }
+
+/// Size, in bytes, of the jump table prefixing a 4-stream huff0 literals section: three
+/// little-endian `u16` stream sizes, the fourth stream taking the remainder of `source`.
+const JUMP_TABLE_SIZE: usize = 6;
+
+/// Decodes a 4-stream huff0 literals section, round-robin across the 4 independent streams so
+/// their bit-reader/decoder chains overlap instead of running fully sequentially.
+pub fn decode_4streams(
+    table: &HuffmanTable,
+    total_output_len: usize,
+    source: &[u8],
+) -> Result<Vec<u8>, HuffmanTableError> {
+    use HuffmanTableError as err;
+
+    if source.len() < JUMP_TABLE_SIZE {
+        return Err(err::NotEnoughBytesInSource {
+            got: source.len(),
+            need: JUMP_TABLE_SIZE,
+        });
+    }
+
+    let size1 = u16::from_le_bytes([source[0], source[1]]) as usize;
+    let size2 = u16::from_le_bytes([source[2], source[3]]) as usize;
+    let size3 = u16::from_le_bytes([source[4], source[5]]) as usize;
+
+    let available = source.len() - JUMP_TABLE_SIZE;
+    let declared = size1 + size2 + size3;
+    if declared > available {
+        return Err(err::FourStreamSizesExceedSource {
+            declared,
+            available,
+        });
+    }
+    let size4 = available - declared;
+
+    let mut offset = JUMP_TABLE_SIZE;
+    let stream1 = &source[offset..offset + size1];
+    offset += size1;
+    let stream2 = &source[offset..offset + size2];
+    offset += size2;
+    let stream3 = &source[offset..offset + size3];
+    offset += size3;
+    let stream4 = &source[offset..offset + size4];
+
+    let symbols_per_stream = total_output_len.div_ceil(4);
+    let last_stream_symbols = total_output_len
+        .checked_sub(symbols_per_stream * 3)
+        .ok_or(err::FourStreamSymbolCountUnderflow {
+            total_symbols: total_output_len,
+            symbols_per_stream,
+        })?;
+
+    let mut br1 = BitReaderReversed::new(stream1);
+    let mut br2 = BitReaderReversed::new(stream2);
+    let mut br3 = BitReaderReversed::new(stream3);
+    let mut br4 = BitReaderReversed::new(stream4);
+
+    let mut dec1 = HuffmanDecoder::new(table);
+    let mut dec2 = HuffmanDecoder::new(table);
+    let mut dec3 = HuffmanDecoder::new(table);
+    let mut dec4 = HuffmanDecoder::new(table);
+
+    dec1.init_state(&mut br1)?;
+    dec2.init_state(&mut br2)?;
+    dec3.init_state(&mut br3)?;
+    dec4.init_state(&mut br4)?;
+
+    let mut out1 = Vec::with_capacity(symbols_per_stream);
+    let mut out2 = Vec::with_capacity(symbols_per_stream);
+    let mut out3 = Vec::with_capacity(symbols_per_stream);
+    let mut out4 = Vec::with_capacity(last_stream_symbols);
+
+    for i in 0..symbols_per_stream {
+        out1.push(dec1.decode_symbol());
+        dec1.next_state(&mut br1)?;
+
+        out2.push(dec2.decode_symbol());
+        dec2.next_state(&mut br2)?;
+
+        out3.push(dec3.decode_symbol());
+        dec3.next_state(&mut br3)?;
+
+        if i < last_stream_symbols {
+            out4.push(dec4.decode_symbol());
+            dec4.next_state(&mut br4)?;
+        }
+    }
+
+    // a stream read more than one bit past its end is reported as overconsumed, so a
+    // corrupted/truncated jump table that still happens to satisfy every individual `get_bits`
+    // call errors here instead of decoding silently wrong. Bits left unread at the end of a
+    // stream are not checked and produce no error.
+    for (stream_index, br) in [&br1, &br2, &br3, &br4].into_iter().enumerate() {
+        if br.bits_remaining() < -1 {
+            return Err(err::FourStreamOverconsumed {
+                stream_index: stream_index as u8,
+                bits_remaining: br.bits_remaining(),
+            });
+        }
+    }
+
+    let mut out = Vec::with_capacity(total_output_len);
+    out.extend_from_slice(&out1);
+    out.extend_from_slice(&out2);
+    out.extend_from_slice(&out3);
+    out.extend_from_slice(&out4);
+    Ok(out)
+}
+
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ReusableHuffmanDecoderError {
+    /// [`ReusableHuffmanDecoder::reuse`] was called before
+    /// [`ReusableHuffmanDecoder::build_new`] ever built a table.
+    NoTableBuiltYet,
+}
+
+impl core::fmt::Display for ReusableHuffmanDecoderError {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ReusableHuffmanDecoderError::NoTableBuiltYet => write!(
+                f,
+                "tried to reuse a huffman table (Treeless literals block) before any table was built"
+            ),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl StdError for ReusableHuffmanDecoderError {
+    fn source(&self) -> Option<&(dyn StdError + 'static)> {
+        None
+    }
+}
+
+/// Owns a single [`HuffmanTable`] across blocks, for zstd's "Treeless" literals mode, which
+/// reuses whichever huffman table the previous literals block built.
+pub struct ReusableHuffmanDecoder {
+    table: HuffmanTable,
+    has_table: bool,
+}
+
+impl Default for ReusableHuffmanDecoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReusableHuffmanDecoder {
+    pub fn new() -> Self {
+        ReusableHuffmanDecoder {
+            table: HuffmanTable::new(),
+            has_table: false,
+        }
+    }
+
+    /// Builds a fresh table from a literals block that carries its own weight header,
+    /// replacing whatever table (if any) was cached from a previous block.
+    ///
+    /// Builds into a scratch table first, so a malformed weight header on this call leaves
+    /// the last-good cached table (and `has_table`) untouched instead of being left half-built.
+    pub fn build_new(&mut self, source: &[u8]) -> Result<u32, HuffmanTableError> {
+        let mut scratch = HuffmanTable::new();
+        let bytes_used = scratch.build_decoder(source)?;
+        self.table.reinit_from(&scratch);
+        self.has_table = true;
+        Ok(bytes_used)
+    }
+
+    /// Returns the table cached from the most recent [`ReusableHuffmanDecoder::build_new`]
+    /// call, for a Treeless literals block that declares no weights of its own.
+    pub fn reuse(&self) -> Result<&HuffmanTable, ReusableHuffmanDecoderError> {
+        if self.has_table {
+            Ok(&self.table)
+        } else {
+            Err(ReusableHuffmanDecoderError::NoTableBuiltYet)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn build_double_decode_table_on_fresh_table_does_not_panic() {
+        let mut table = HuffmanTable::new();
+        table.build_double_decode_table();
+        assert!(table.decode_double.is_empty());
+    }
+
+    #[test]
+    fn decode_4streams_rejects_undersized_source() {
+        let table = HuffmanTable::new();
+        let err = decode_4streams(&table, 4, &[0u8; 4]).unwrap_err();
+        assert!(matches!(err, HuffmanTableError::NotEnoughBytesInSource { .. }));
+    }
+
+    #[test]
+    fn decode_4streams_rejects_jump_table_exceeding_source() {
+        let table = HuffmanTable::new();
+        // declares 10 bytes of stream data but only 6 bytes (the jump table itself) are given
+        let source = [10u8, 0, 0, 0, 0, 0];
+        let err = decode_4streams(&table, 4, &source).unwrap_err();
+        assert!(matches!(
+            err,
+            HuffmanTableError::FourStreamSizesExceedSource { .. }
+        ));
+    }
+
+    #[test]
+    fn decode_4streams_rejects_symbol_count_too_small_for_4_streams() {
+        let table = HuffmanTable::new();
+        let source = [0u8; JUMP_TABLE_SIZE];
+        let err = decode_4streams(&table, 1, &source).unwrap_err();
+        assert!(matches!(
+            err,
+            HuffmanTableError::FourStreamSymbolCountUnderflow { .. }
+        ));
+    }
+
+    #[test]
+    fn reusable_huffman_decoder_errors_before_first_build() {
+        let decoder = ReusableHuffmanDecoder::new();
+        assert!(matches!(
+            decoder.reuse(),
+            Err(ReusableHuffmanDecoderError::NoTableBuiltYet)
+        ));
+    }
+
+    /// Builds a `BitReaderReversed`-compatible bitstream for `symbols` under `encoder`: codes
+    /// are written in reverse (so the reversed reader recovers them in original order) and
+    /// front-padded out to a whole number of bytes, so the unwritten high bits of the last byte
+    /// ForwardBitWriter allocates - which read *first* - can't be mistaken for real code bits.
+    fn encode_reversed_for_reading(encoder: &super::super::huff0_encoder::HuffmanEncoder, symbols: &[u8]) -> Vec<u8> {
+        use super::super::huff0_encoder::ForwardBitWriter;
+
+        let real_bits: u32 = symbols
+            .iter()
+            .map(|&s| encoder.code_for(s).unwrap().1 as u32)
+            .sum();
+        // a full byte of slack so a trailing over-read (`next_state`/`next_pair` always
+        // consuming one more step's worth of bits than strictly needed near the end of a
+        // stream) lands on real, if unused, bits instead of running past the buffer.
+        let front_padding = 8 + (8 - real_bits % 8) % 8;
+
+        let mut writer = ForwardBitWriter::new();
+        writer.write_bits(0, front_padding);
+        for &symbol in symbols.iter().rev() {
+            let (code, num_bits) = encoder.code_for(symbol).unwrap();
+            writer.write_bits(code as u32, num_bits as u32);
+        }
+        writer.finish()
+    }
+
+    #[test]
+    fn decode_pair_round_trips_a_real_bitstream() {
+        let mut histogram = [0u32; 256];
+        for &b in b"aaaabbcc" {
+            histogram[b as usize] += 1;
+        }
+        let mut table = HuffmanTable::new();
+        let encoder = table.build_encoder_from_histogram(&histogram).unwrap();
+        table.build_double_decode_table();
+        assert!(table.max_num_bits >= 2, "need a multi-bit code for a real double-decode step");
+
+        let data = b"abcabcaabbccabca";
+        let bytes = encode_reversed_for_reading(&encoder, data);
+
+        let mut br = BitReaderReversed::new(&bytes);
+        let mut decoder = HuffmanDecoder::new(&table);
+        decoder.init_state(&mut br).unwrap();
+
+        let mut decoded = Vec::new();
+        while decoded.len() < data.len() {
+            let (first, second, _bits_consumed) = decoder.next_pair(&mut br).unwrap();
+            decoded.push(first);
+            if let Some(second) = second {
+                decoded.push(second);
+            }
+        }
+        decoded.truncate(data.len());
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn decode_4streams_round_trips_a_two_symbol_alphabet() {
+        let mut histogram = [0u32; 256];
+        histogram[b'A' as usize] = 5;
+        histogram[b'B' as usize] = 3;
+        let mut table = HuffmanTable::new();
+        let encoder = table.build_encoder_from_histogram(&histogram).unwrap();
+
+        let data = b"AABABABBAABBBABA";
+        assert_eq!(data.len() % 4, 0);
+        let symbols_per_stream = data.len() / 4;
+
+        // `decode_4streams` always runs one extra `next_state` past the last decoded symbol of
+        // each stream (it doesn't know in advance which step is the last one); the front padding
+        // `encode_reversed_for_reading` adds gives that trailing read real bits to consume
+        // instead of running past the end of the stream's slice.
+        let mut stream_bytes = Vec::new();
+        for chunk in data.chunks(symbols_per_stream) {
+            stream_bytes.push(encode_reversed_for_reading(&encoder, chunk));
+        }
+
+        let mut source = Vec::new();
+        for bytes in &stream_bytes[..3] {
+            source.extend_from_slice(&(bytes.len() as u16).to_le_bytes());
+        }
+        for bytes in &stream_bytes {
+            source.extend_from_slice(bytes);
+        }
+
+        let decoded = decode_4streams(&table, data.len(), &source).unwrap();
+        assert_eq!(decoded, data);
+    }
+
+    #[test]
+    fn reusable_huffman_decoder_returns_built_table() {
+        let mut histogram = [0u32; 256];
+        for &b in b"aaaabbbbcccd" {
+            histogram[b as usize] += 1;
+        }
+        let mut table = HuffmanTable::new();
+        table.build_encoder_from_histogram(&histogram).unwrap();
+        let weight_header = table.write_weights().unwrap();
+
+        let mut decoder = ReusableHuffmanDecoder::new();
+        decoder.build_new(&weight_header).unwrap();
+        assert!(decoder.reuse().is_ok());
+    }
+}
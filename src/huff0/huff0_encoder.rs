@@ -0,0 +1,624 @@
+use super::huff0_decoder::{HuffmanTable, HuffmanTableError, MAX_MAX_NUM_BITS};
+use alloc::collections::BinaryHeap;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+
+/// Above this many weights, the direct 4-bit nibble header can no longer address `num_weights`.
+const MAX_DIRECT_WEIGHTS: usize = 128;
+
+/// A symbol -> code mapping built by [`HuffmanTable::build_encoder_from_histogram`].
+pub struct HuffmanEncoder {
+    codes: Vec<CodeEntry>,
+    pub max_num_bits: u8,
+}
+
+#[derive(Copy, Clone, Default)]
+struct CodeEntry {
+    code: u16,
+    num_bits: u8,
+}
+
+impl HuffmanEncoder {
+    /// Returns `(code, num_bits)` to write for `symbol`, or `None` if it never appeared
+    /// in the histogram this encoder was built from. Write the bits most-significant-bit
+    /// first, to match huff0's bit order.
+    pub fn code_for(&self, symbol: u8) -> Option<(u16, u8)> {
+        let entry = self.codes[symbol as usize];
+        if entry.num_bits == 0 {
+            None
+        } else {
+            Some((entry.code, entry.num_bits))
+        }
+    }
+}
+
+impl HuffmanTable {
+    /// Builds a canonical huffman table from a 256-entry symbol frequency histogram, storing
+    /// the weight table on `self` and returning an [`HuffmanEncoder`] for the actual codes.
+    pub fn build_encoder_from_histogram(
+        &mut self,
+        histogram: &[u32; 256],
+    ) -> Result<HuffmanEncoder, HuffmanTableError> {
+        use HuffmanTableError as err;
+
+        let present: Vec<(u8, u32)> = histogram
+            .iter()
+            .enumerate()
+            .filter(|&(_, &freq)| freq > 0)
+            .map(|(symbol, &freq)| (symbol as u8, freq))
+            .collect();
+
+        if present.is_empty() {
+            return Err(err::MissingWeights);
+        }
+
+        // the shared weight math always infers the *last* symbol's weight from the others, so
+        // a lone present symbol can't be the only stored entry; pair it with an unused
+        // companion byte so both get a real 1-bit code.
+        let lengths = if present.len() == 1 {
+            let symbol = present[0].0;
+            let companion = if symbol == 0 { 1 } else { 0 };
+            let mut lengths = [0u8; 256];
+            lengths[symbol as usize] = 1;
+            lengths[companion as usize] = 1;
+            lengths
+        } else {
+            canonical_lengths(&present, MAX_MAX_NUM_BITS)
+        };
+
+        self.build_from_lengths(&lengths)
+    }
+
+    /// Turns a per-symbol code-length table (0 meaning "absent") into the weight
+    /// representation, stores it on `self`, and rebuilds the decode table from it.
+    fn build_from_lengths(
+        &mut self,
+        lengths: &[u8; 256],
+    ) -> Result<HuffmanEncoder, HuffmanTableError> {
+        use HuffmanTableError as err;
+
+        let max_bits = lengths.iter().copied().max().unwrap_or(0);
+        if max_bits == 0 {
+            return Err(err::MissingWeights);
+        }
+
+        let max_symbol = lengths
+            .iter()
+            .rposition(|&len| len > 0)
+            .expect("max_bits > 0 implies at least one present symbol");
+
+        // weights for symbols [0, max_symbol) are stored; the weight of `max_symbol` itself
+        // is inferred from the power-of-two leftover, exactly like `read_weights` expects.
+        let mut weights = Vec::with_capacity(max_symbol);
+        for &len in &lengths[..max_symbol] {
+            weights.push(if len > 0 { max_bits + 1 - len } else { 0 });
+        }
+        self.weights.clear();
+        self.weights.extend_from_slice(&weights);
+
+        self.build_table_from_weights()?;
+
+        Ok(HuffmanEncoder {
+            codes: canonical_codes(lengths),
+            max_num_bits: max_bits,
+        })
+    }
+
+    /// Serializes `self.weights` into the header `read_weights` decodes: a header byte
+    /// followed by either 4-bit packed nibbles or an FSE-compressed weight stream.
+    pub fn write_weights(&self) -> Result<Vec<u8>, HuffmanTableError> {
+        use HuffmanTableError as err;
+
+        let num_weights = self.weights.len();
+        if num_weights == 0 {
+            return Err(err::MissingWeights);
+        }
+
+        if num_weights <= MAX_DIRECT_WEIGHTS {
+            let header = num_weights as u8 + 127;
+            let mut out = Vec::with_capacity(1 + num_weights.div_ceil(2));
+            out.push(header);
+            for pair in self.weights.chunks(2) {
+                let high = pair[0];
+                let low = pair.get(1).copied().unwrap_or(0);
+                out.push((high << 4) | low);
+            }
+            return Ok(out);
+        }
+
+        let compressed = fse_compress_weights(&self.weights);
+        if compressed.len() >= 128 {
+            // the header byte can only express compressed lengths < 128; a real huff0
+            // encoder would fall back to raw literals or a smaller table shape here, but
+            // running out of room means the weight distribution is nearly flat, which
+            // shouldn't happen once FSE compression is doing its job
+            return Err(err::FSETableUsedTooManyBytes {
+                used: compressed.len(),
+                available_bytes: 127,
+            });
+        }
+
+        let mut out = Vec::with_capacity(1 + compressed.len());
+        out.push(compressed.len() as u8);
+        out.extend_from_slice(&compressed);
+        Ok(out)
+    }
+}
+
+/// Writes bits least-significant-bit first, the order the FSE table description/bitstream use.
+///
+/// `pub(crate)` so decoder-side tests can build a matching bitstream without a public API for it.
+pub(crate) struct ForwardBitWriter {
+    bytes: Vec<u8>,
+    bit_pos: u32,
+}
+
+impl ForwardBitWriter {
+    pub(crate) fn new() -> Self {
+        Self {
+            bytes: Vec::new(),
+            bit_pos: 0,
+        }
+    }
+
+    pub(crate) fn write_bits(&mut self, value: u32, num_bits: u32) {
+        for i in 0..num_bits {
+            if self.bit_pos == 0 {
+                self.bytes.push(0);
+            }
+            let bit = ((value >> i) & 1) as u8;
+            let last = self.bytes.len() - 1;
+            self.bytes[last] |= bit << self.bit_pos;
+            self.bit_pos = (self.bit_pos + 1) % 8;
+        }
+    }
+
+    /// Pads with zero bits up to the next byte boundary.
+    fn pad_to_byte(&mut self) {
+        while self.bit_pos != 0 {
+            self.write_bits(0, 1);
+        }
+    }
+
+    pub(crate) fn finish(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+/// FSE-compresses a weight stream for [`HuffmanTable::write_weights`], encoding symbols in
+/// reverse so a forward read against the same normalized counts recovers them in order.
+fn fse_compress_weights(weights: &[u8]) -> Vec<u8> {
+    let max_symbol = weights.iter().copied().max().unwrap_or(0) as usize;
+    let mut histogram = vec![0u32; max_symbol + 1];
+    for &w in weights {
+        histogram[w as usize] += 1;
+    }
+
+    let table_log = choose_table_log(&histogram, weights.len());
+    let table_size = 1u32 << table_log;
+    let norm_counts = normalize_counts(&histogram, weights.len(), table_size);
+
+    let mut writer = ForwardBitWriter::new();
+    write_ncount(&mut writer, &norm_counts, table_log);
+    // `read_weights` starts the compressed stream at the byte after the table description
+    writer.pad_to_byte();
+
+    let table = EncodeTable::build(&norm_counts, table_log);
+    // `read_weights` alternates two decoders, the first taking even weight indexes and the
+    // second odd ones, so each index parity gets its own encoder state.
+    let mut states: [Option<u32>; 2] = [None, None];
+    for (idx, &w) in weights.iter().enumerate().rev() {
+        states[idx % 2] = Some(match states[idx % 2] {
+            // a state's first symbol transition writes no bits: the decoder's `FSE_initDState`
+            // recovers this starting state by reading `table_log` bits directly, rather than
+            // replaying an `encode_symbol` step against an arbitrary seed state.
+            None => table.init_state(w),
+            Some(state) => table.encode_symbol(state, w, &mut writer),
+        });
+    }
+    // the even-index state is written last, so it is the first one the decoder reads
+    writer.write_bits(states[1].unwrap_or(table_size), table_log as u32);
+    writer.write_bits(states[0].unwrap_or(table_size), table_log as u32);
+
+    // `read_weights` scans the compressed stream back to front looking for this sentinel bit
+    // to know where the real content starts past the zero-padding to the next byte boundary.
+    writer.write_bits(1, 1);
+    writer.pad_to_byte();
+
+    writer.finish()
+}
+
+/// Writes the normalized-count table description `FSE_readNCount` expects, including the
+/// adaptive short/long field width and the repeat-zero run encoding.
+fn write_ncount(writer: &mut ForwardBitWriter, norm_counts: &[i32], table_log: u8) {
+    writer.write_bits(table_log as u32 - MIN_TABLE_LOG as u32, 4);
+
+    let mut remaining: i64 = (1i64 << table_log) + 1;
+    let mut threshold: i64 = 1i64 << table_log;
+    let mut nb_bits: u32 = table_log as u32 + 1;
+
+    let mut symbol = 0usize;
+    while remaining > 1 && symbol < norm_counts.len() {
+        if symbol > 0 && norm_counts[symbol - 1] == 0 {
+            let run_start = symbol;
+            while symbol < norm_counts.len() && norm_counts[symbol] == 0 {
+                symbol += 1;
+            }
+            let mut run_len = symbol - run_start;
+            while run_len >= 3 {
+                writer.write_bits(3, 2);
+                run_len -= 3;
+            }
+            writer.write_bits(run_len as u32, 2);
+            if symbol >= norm_counts.len() || remaining <= 1 {
+                break;
+            }
+        }
+
+        let count = norm_counts[symbol] as i64;
+        let max = 2 * threshold - 1 - remaining;
+        let value = count + 1;
+        if value < max {
+            writer.write_bits(value as u32, nb_bits - 1);
+        } else {
+            let adjusted = if value < threshold { value } else { value + max };
+            writer.write_bits(adjusted as u32, nb_bits);
+        }
+
+        remaining -= count.abs();
+        symbol += 1;
+
+        while remaining < threshold {
+            nb_bits -= 1;
+            threshold >>= 1;
+        }
+    }
+}
+
+const MIN_TABLE_LOG: u8 = 5;
+// the huffman weight FSE table is a fixed, small (<=12-symbol) alphabet; the zstd format caps
+// its accuracy log at 6, unlike the 9-bit tables used for sequence compression.
+const MAX_TABLE_LOG: u8 = 6;
+
+fn choose_table_log(histogram: &[u32], total: usize) -> u8 {
+    let max_symbol = histogram.len().saturating_sub(1);
+    let min_for_symbols = highest_bit_set(max_symbol as u32 + 1);
+    let min_for_total = highest_bit_set(total as u32).min(MAX_TABLE_LOG as u32);
+    (min_for_symbols.max(min_for_total) as u8).clamp(MIN_TABLE_LOG, MAX_TABLE_LOG)
+}
+
+fn highest_bit_set(x: u32) -> u32 {
+    if x == 0 {
+        0
+    } else {
+        u32::BITS - x.leading_zeros()
+    }
+}
+
+/// Scales `histogram` down to normalized counts summing to `table_size`; a present symbol that
+/// would round to zero gets the reserved `-1` ("less than one, but present") marker instead.
+fn normalize_counts(histogram: &[u32], total: usize, table_size: u32) -> Vec<i32> {
+    let mut counts = vec![0i32; histogram.len()];
+    let mut remaining = table_size as i64;
+    let mut largest_symbol = 0;
+    let mut largest_count = 0i64;
+
+    for (symbol, &freq) in histogram.iter().enumerate() {
+        if freq == 0 {
+            continue;
+        }
+        let scaled = (freq as i64 * table_size as i64) / total as i64;
+        if scaled == 0 {
+            counts[symbol] = -1;
+            remaining -= 1;
+        } else {
+            counts[symbol] = scaled as i32;
+            remaining -= scaled;
+            if scaled > largest_count {
+                largest_count = scaled;
+                largest_symbol = symbol;
+            }
+        }
+    }
+
+    // dump any rounding slack onto the most frequent symbol, the same "largest absorbs the
+    // remainder" trick FSE's own normalization uses
+    counts[largest_symbol] += remaining as i32;
+    counts
+}
+
+/// Per-symbol FSE encode transition data, mirroring the reference `FSE_symbolCompressionTransform`
+/// table built by `FSE_buildCTable`.
+struct EncodeTable {
+    delta_nb_bits: Vec<u32>,
+    delta_find_state: Vec<i32>,
+    state_table: Vec<u16>,
+}
+
+impl EncodeTable {
+    fn build(norm_counts: &[i32], table_log: u8) -> Self {
+        let table_size = 1usize << table_log;
+        let num_symbols = norm_counts.len();
+        let mask = table_size - 1;
+        let step = (table_size >> 1) + (table_size >> 3) + 3;
+
+        let mut cumul = vec![0i32; num_symbols + 1];
+        let mut table_symbol = vec![0u8; table_size];
+        let mut high_threshold = table_size - 1;
+
+        for (symbol, &count) in norm_counts.iter().enumerate() {
+            if count == -1 {
+                cumul[symbol + 1] = cumul[symbol] + 1;
+                table_symbol[high_threshold] = symbol as u8;
+                high_threshold -= 1;
+            } else {
+                cumul[symbol + 1] = cumul[symbol] + count.max(0);
+            }
+        }
+
+        // spread each symbol's remaining occurrences across the table using FSE's standard
+        // "well-mixed" stride; low-probability (-1) symbols were already seeded above.
+        let mut position = 0usize;
+        for (symbol, &count) in norm_counts.iter().enumerate() {
+            for _ in 0..count.max(0) {
+                table_symbol[position] = symbol as u8;
+                position = (position + step) & mask;
+                while position > high_threshold {
+                    position = (position + step) & mask;
+                }
+            }
+        }
+
+        let mut next_rank = cumul.clone();
+        let mut state_table = vec![0u16; table_size];
+        for (u, &symbol) in table_symbol.iter().enumerate() {
+            let rank = next_rank[symbol as usize] as usize;
+            next_rank[symbol as usize] += 1;
+            state_table[rank] = (table_size + u) as u16;
+        }
+
+        let mut delta_nb_bits = vec![0u32; num_symbols];
+        let mut delta_find_state = vec![0i32; num_symbols];
+        for (symbol, &count) in norm_counts.iter().enumerate() {
+            match count {
+                0 => {}
+                1 | -1 => {
+                    delta_nb_bits[symbol] =
+                        ((table_log as u32) << 16).wrapping_sub(1 << table_log);
+                    delta_find_state[symbol] = cumul[symbol] - 1;
+                }
+                count => {
+                    let count = count as u32;
+                    // `highest_bit_set` returns a bit *length* (1-indexed); this formula wants
+                    // the 0-indexed position of the top bit, hence the extra `+ 1`.
+                    let max_bits_out = table_log as u32 + 1 - highest_bit_set(count - 1);
+                    let min_state_plus = count << max_bits_out;
+                    delta_nb_bits[symbol] = (max_bits_out << 16).wrapping_sub(min_state_plus);
+                    delta_find_state[symbol] = cumul[symbol] - count as i32;
+                }
+            }
+        }
+
+        Self {
+            delta_nb_bits,
+            delta_find_state,
+            state_table,
+        }
+    }
+
+    fn encode_symbol(&self, state: u32, symbol: u8, writer: &mut ForwardBitWriter) -> u32 {
+        let delta_nb_bits = self.delta_nb_bits[symbol as usize];
+        let nb_bits_out = state.wrapping_add(delta_nb_bits) >> 16;
+        writer.write_bits(state, nb_bits_out);
+        let rank = (state >> nb_bits_out) as i32 + self.delta_find_state[symbol as usize];
+        self.state_table[rank as usize] as u32
+    }
+
+    /// Computes the FSE state for the very first symbol encoded, matching `FSE_initCState2`:
+    /// no bits are written for this transition, so instead of taking an input state like
+    /// `encode_symbol`, it picks the symbol's starting state from its transition data alone.
+    fn init_state(&self, symbol: u8) -> u32 {
+        let delta_nb_bits = self.delta_nb_bits[symbol as usize];
+        let nb_bits_out = delta_nb_bits.wrapping_add(1 << 15) >> 16;
+        let rank = ((nb_bits_out << 16).wrapping_sub(delta_nb_bits) >> nb_bits_out) as i32
+            + self.delta_find_state[symbol as usize];
+        self.state_table[rank as usize] as u32
+    }
+}
+
+/// Assigns codes given a per-symbol length table, matching the rank layout
+/// [`HuffmanTable::build_table_from_weights`] fills its decode table with: codes aren't numbered
+/// in plain ascending canonical order, they're the table index each symbol's range starts at,
+/// shifted down to its own bit width. Longer codes land in the low end of the index space,
+/// shorter ones in the high end, since that's the order the decode table itself uses.
+fn canonical_codes(lengths: &[u8; 256]) -> Vec<CodeEntry> {
+    let max_bits = lengths.iter().copied().max().unwrap_or(0) as usize;
+    if max_bits == 0 {
+        return vec![CodeEntry::default(); 256];
+    }
+
+    let mut bit_ranks = vec![0u32; max_bits + 1];
+    for &len in lengths {
+        bit_ranks[len as usize] += 1;
+    }
+
+    let mut rank_indexes = vec![0usize; max_bits + 1];
+    rank_indexes[max_bits] = 0;
+    for bits in (1..=max_bits).rev() {
+        rank_indexes[bits - 1] =
+            rank_indexes[bits] + bit_ranks[bits] as usize * (1 << (max_bits - bits));
+    }
+
+    let mut codes = vec![CodeEntry::default(); 256];
+    for (symbol, &len) in lengths.iter().enumerate() {
+        if len == 0 {
+            continue;
+        }
+        let len = len as usize;
+        let slot_len = 1usize << (max_bits - len);
+        let base_idx = rank_indexes[len];
+        rank_indexes[len] += slot_len;
+        codes[symbol] = CodeEntry {
+            code: (base_idx / slot_len) as u16,
+            num_bits: len as u8,
+        };
+    }
+    codes
+}
+
+/// Builds canonical huffman code lengths for `present` symbols, limiting the longest code to
+/// `limit` bits via the same "borrow from a shallower leaf" rebalancing zlib's `gen_bitlen` uses.
+fn canonical_lengths(present: &[(u8, u32)], limit: u8) -> [u8; 256] {
+    let mut node_count = present.len();
+    let mut parent: Vec<Option<usize>> = vec![None; node_count];
+
+    // min-heap on (freq, insertion order, node index); insertion order keeps ties
+    // deterministic so repeated builds over the same histogram are reproducible.
+    let mut heap: BinaryHeap<Reverse<(u64, u32, usize)>> = present
+        .iter()
+        .enumerate()
+        .map(|(idx, &(_, freq))| Reverse((freq as u64, idx as u32, idx)))
+        .collect();
+    let mut next_seq = node_count as u32;
+
+    while heap.len() > 1 {
+        let Reverse((freq_a, _, idx_a)) = heap.pop().unwrap();
+        let Reverse((freq_b, _, idx_b)) = heap.pop().unwrap();
+
+        let new_idx = node_count;
+        node_count += 1;
+        parent.push(None);
+        parent[idx_a] = Some(new_idx);
+        parent[idx_b] = Some(new_idx);
+
+        heap.push(Reverse((freq_a + freq_b, next_seq, new_idx)));
+        next_seq += 1;
+    }
+
+    let natural_len: Vec<u32> = (0..present.len())
+        .map(|leaf| {
+            let mut depth = 0;
+            let mut cur = leaf;
+            while let Some(p) = parent[cur] {
+                depth += 1;
+                cur = p;
+            }
+            depth
+        })
+        .collect();
+
+    let limit = limit as usize;
+    let mut length_counts = vec![0u32; limit + 1];
+    let mut overflow: i64 = 0;
+    for &len in &natural_len {
+        let len = if len as usize > limit {
+            overflow += 1;
+            limit
+        } else {
+            len as usize
+        };
+        length_counts[len] += 1;
+    }
+
+    while overflow > 0 {
+        let mut bits = limit - 1;
+        while length_counts[bits] == 0 {
+            bits -= 1;
+        }
+        length_counts[bits] -= 1;
+        length_counts[bits + 1] += 2;
+        length_counts[limit] -= 1;
+        overflow -= 2;
+    }
+
+    // re-assign the repaired length distribution to symbols: most frequent symbols get the
+    // shortest lengths, mirroring the canonical huffman convention.
+    let mut order: Vec<usize> = (0..present.len()).collect();
+    order.sort_by(|&a, &b| {
+        present[b]
+            .1
+            .cmp(&present[a].1)
+            .then_with(|| present[a].0.cmp(&present[b].0))
+    });
+    let mut order = order.into_iter();
+
+    let mut lengths = [0u8; 256];
+    for (len, &count) in length_counts.iter().enumerate().skip(1) {
+        for _ in 0..count {
+            let sym_idx = order
+                .next()
+                .expect("length_counts must account for every present symbol");
+            lengths[present[sym_idx].0 as usize] = len as u8;
+        }
+    }
+    lengths
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn histogram_of(data: &[u8]) -> [u32; 256] {
+        let mut histogram = [0u32; 256];
+        for &b in data {
+            histogram[b as usize] += 1;
+        }
+        histogram
+    }
+
+    fn round_trip(data: &[u8]) {
+        let mut encoder_table = HuffmanTable::new();
+        let histogram = histogram_of(data);
+        let encoder = encoder_table.build_encoder_from_histogram(&histogram).unwrap();
+        let weight_header = encoder_table.write_weights().unwrap();
+
+        let mut decoder_table = HuffmanTable::new();
+        decoder_table.build_decoder(&weight_header).unwrap();
+
+        for &symbol in data {
+            let (_, encoder_bits) = encoder.code_for(symbol).unwrap();
+            assert_eq!(decoder_table.bits[symbol as usize], encoder_bits);
+        }
+    }
+
+    #[test]
+    fn round_trips_weights_through_direct_nibble_header() {
+        round_trip(b"abracadabra mississippi banana");
+    }
+
+    #[test]
+    fn single_symbol_histogram_is_encodable() {
+        let mut table = HuffmanTable::new();
+        let mut histogram = [0u32; 256];
+        histogram[b'x' as usize] = 42;
+        let encoder = table.build_encoder_from_histogram(&histogram).unwrap();
+        let (_, num_bits) = encoder.code_for(b'x').unwrap();
+        assert_eq!(num_bits, 1);
+
+        let weight_header = table.write_weights().unwrap();
+        let mut decoder_table = HuffmanTable::new();
+        decoder_table.build_decoder(&weight_header).unwrap();
+    }
+
+    #[test]
+    fn round_trips_weights_through_fse_compressed_header() {
+        // more than MAX_DIRECT_WEIGHTS distinct byte values forces the FSE-compressed path
+        let data: Vec<u8> = (0..=200u8).chain(0..=200u8).chain(0..50u8).collect();
+        round_trip(&data);
+    }
+
+    #[test]
+    fn most_frequent_symbol_gets_shortest_code() {
+        let mut table = HuffmanTable::new();
+        let histogram = histogram_of(b"aaaaaaaabbbbccd");
+        let encoder = table.build_encoder_from_histogram(&histogram).unwrap();
+
+        let shortest = b"abcd"
+            .iter()
+            .map(|&s| encoder.code_for(s).unwrap().1)
+            .min()
+            .unwrap();
+        assert_eq!(encoder.code_for(b'a').unwrap().1, shortest);
+    }
+}